@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use color_eyre::Result;
+
+use crate::ruleset::{CombatRules, PlayerStats, RankRange, RuleSet};
+use crate::strategy::{GreedyStrategy, RandomStrategy, Strategy};
+
+#[derive(Parser)]
+#[command(author, version, about)]
+pub(crate) struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// Seed the dungeon shuffle for a reproducible game. Only the deal is
+    /// reproducible: under `--combat d20` the attribute-check rolls still
+    /// come from an unseeded RNG, so those games vary run to run.
+    #[arg(long)]
+    pub(crate) seed: Option<u64>,
+
+    /// Record every move of an interactive game to this file for later
+    /// replay. Only supported under `--combat deterministic`, since d20
+    /// combat rolls aren't reproducible and a replay of them would diverge.
+    #[arg(long, conflicts_with = "replay")]
+    pub(crate) record: Option<PathBuf>,
+
+    /// Replay a previously recorded sequence of moves instead of playing
+    /// interactively. Rejects logs recorded under `--combat d20` for the
+    /// same reproducibility reason as `--record`.
+    #[arg(long)]
+    pub(crate) replay: Option<PathBuf>,
+
+    /// Resume a game previously written by `--save`, instead of dealing a fresh one.
+    #[arg(long, conflicts_with_all = ["seed", "replay", "record"])]
+    pub(crate) load: Option<PathBuf>,
+
+    /// Lets you save and quit mid-game, writing it to this file so it can be
+    /// resumed with `--load`.
+    #[arg(long)]
+    pub(crate) save: Option<PathBuf>,
+
+    /// Instead of playing, print the maximum score achievable from the deal.
+    #[arg(long)]
+    pub(crate) solve: bool,
+
+    /// Annotate each card prompt with the solver's best-case score for it.
+    #[arg(long)]
+    pub(crate) hint: bool,
+
+    /// How monster cards are resolved.
+    #[arg(long, value_enum, default_value_t = CombatKind::Deterministic)]
+    pub(crate) combat: CombatKind,
+
+    /// Player body stat, used by `--combat d20`.
+    #[arg(long, default_value_t = 10)]
+    pub(crate) body: u8,
+
+    /// Player strength stat, used by `--combat d20`.
+    #[arg(long, default_value_t = 10)]
+    pub(crate) strength: u8,
+
+    /// Player toughness stat, used by `--combat d20`.
+    #[arg(long, default_value_t = 10)]
+    pub(crate) toughness: u8,
+
+    /// Load the full rule set from a JSON config file, taking precedence
+    /// over every other rule-set flag below.
+    #[arg(long)]
+    pub(crate) rules: Option<PathBuf>,
+
+    /// Starting and maximum health.
+    #[arg(long)]
+    pub(crate) max_health: Option<u8>,
+
+    /// How many cards are dealt into a room.
+    #[arg(long)]
+    pub(crate) room_size: Option<usize>,
+
+    /// Deal face cards and aces for hearts/diamonds too, instead of just 2-10.
+    #[arg(long)]
+    pub(crate) full_red_ranks: bool,
+
+    /// Add a pair of high-value bonus potions to the dungeon.
+    #[arg(long)]
+    pub(crate) jokers: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum CombatKind {
+    Deterministic,
+    D20,
+}
+
+impl Cli {
+    pub(crate) fn rule_set(&self) -> Result<RuleSet> {
+        if let Some(path) = &self.rules {
+            return RuleSet::load(path);
+        }
+
+        let combat = match self.combat {
+            CombatKind::Deterministic => CombatRules::Deterministic,
+            CombatKind::D20 => CombatRules::D20(PlayerStats {
+                body: self.body,
+                strength: self.strength,
+                toughness: self.toughness,
+            }),
+        };
+
+        let mut rule_set = RuleSet {
+            combat,
+            ..RuleSet::default()
+        };
+        if let Some(max_health) = self.max_health {
+            rule_set.max_health = max_health;
+        }
+        if let Some(room_size) = self.room_size {
+            rule_set.room_size = room_size;
+        }
+        if self.full_red_ranks {
+            rule_set.deck.red_ranks = RankRange::Full;
+        }
+        rule_set.deck.jokers = self.jokers;
+
+        Ok(rule_set)
+    }
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Command {
+    /// Run many headless games and report aggregate statistics.
+    Simulate {
+        /// How many games to play.
+        #[arg(long, default_value_t = 1000)]
+        games: usize,
+        /// Which built-in strategy plays each game.
+        #[arg(long, value_enum, default_value_t = StrategyKind::Greedy)]
+        strategy: StrategyKind,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum StrategyKind {
+    Random,
+    Greedy,
+}
+
+impl StrategyKind {
+    pub(crate) fn build(self) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Random => Box::new(RandomStrategy),
+            StrategyKind::Greedy => Box::new(GreedyStrategy),
+        }
+    }
+}
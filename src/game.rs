@@ -0,0 +1,524 @@
+use std::{fs::File, ops::Neg, path::Path};
+
+use cardpack::{Card, Named, Pile, Rank, Suit, CLUBS, HEARTS, SPADES};
+use color_eyre::{eyre::bail, Result};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ruleset::{do_challenge, CombatRules, PlayerStats, RuleSet};
+use crate::strategy::{GameView, Strategy};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GameResult {
+    pub(crate) outcome: Outcome,
+    pub(crate) score: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Outcome {
+    Won,
+    Lost,
+}
+
+pub(crate) struct Game {
+    dungeon: Pile,
+    weapon: Option<u8>,
+    weakest_killed: Option<u8>,
+    just_avoided_room: bool,
+    room: Pile,
+    health: u8,
+    rule_set: RuleSet,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("dungeon finished")]
+    DungeonFinished,
+    #[error("room unfinished")]
+    RoomUnfinished,
+    #[error("invalid card suit")]
+    InvalidCardSuit,
+    #[error("invalid card rank")]
+    InvalidCardRank,
+}
+
+/// A plain rank+suit representation of a card, the only part of `cardpack`
+/// that round-trips through JSON.
+#[derive(Serialize, Deserialize)]
+struct CardDto {
+    rank: String,
+    suit: String,
+}
+
+impl From<&Card> for CardDto {
+    fn from(card: &Card) -> Self {
+        Self {
+            rank: card.rank.index_default(),
+            suit: card.suit.name.index_default(),
+        }
+    }
+}
+
+impl TryFrom<&CardDto> for Card {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(dto: &CardDto) -> Result<Self> {
+        Ok(Card::new(rank_from_code(&dto.rank)?, suit_from_code(&dto.suit)?))
+    }
+}
+
+fn rank_from_code(code: &str) -> Result<Rank> {
+    use cardpack::cards::rank::*;
+    Ok(match code {
+        "A" => ACE,
+        "K" => KING,
+        "Q" => QUEEN,
+        "J" => JACK,
+        "T" => TEN,
+        "9" => NINE,
+        "8" => EIGHT,
+        "7" => SEVEN,
+        "6" => SIX,
+        "5" => FIVE,
+        "4" => FOUR,
+        "3" => THREE,
+        "2" => TWO,
+        _ => bail!(Error::InvalidCardRank),
+    })
+}
+
+fn suit_from_code(code: &str) -> Result<Suit> {
+    use cardpack::cards::suit::*;
+    Ok(match code {
+        "S" => SPADES,
+        "C" => CLUBS,
+        "H" => HEARTS,
+        "D" => DIAMONDS,
+        _ => bail!(Error::InvalidCardSuit),
+    })
+}
+
+/// The serializable mirror of [`Game`]; `Pile` itself doesn't round-trip
+/// through `serde`, so this holds plain [`CardDto`]s instead.
+#[derive(Serialize, Deserialize)]
+struct GameState {
+    dungeon: Vec<CardDto>,
+    room: Vec<CardDto>,
+    weapon: Option<u8>,
+    weakest_killed: Option<u8>,
+    just_avoided_room: bool,
+    health: u8,
+    rule_set: RuleSet,
+}
+
+impl From<&Game> for GameState {
+    fn from(game: &Game) -> Self {
+        Self {
+            dungeon: game.dungeon.cards().iter().map(CardDto::from).collect(),
+            room: game.room.cards().iter().map(CardDto::from).collect(),
+            weapon: game.weapon,
+            weakest_killed: game.weakest_killed,
+            just_avoided_room: game.just_avoided_room,
+            health: game.health,
+            rule_set: game.rule_set,
+        }
+    }
+}
+
+impl TryFrom<GameState> for Game {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(state: GameState) -> Result<Self> {
+        let mut dungeon = Pile::default();
+        for dto in &state.dungeon {
+            dungeon.push(Card::try_from(dto)?);
+        }
+        let mut room = Pile::default();
+        for dto in &state.room {
+            room.push(Card::try_from(dto)?);
+        }
+        Ok(Self {
+            dungeon,
+            room,
+            weapon: state.weapon,
+            weakest_killed: state.weakest_killed,
+            just_avoided_room: state.just_avoided_room,
+            health: state.health,
+            rule_set: state.rule_set,
+        })
+    }
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameState::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = GameState::deserialize(deserializer)?;
+        Game::try_from(state).map_err(D::Error::custom)
+    }
+}
+
+fn fold_in(cards: &mut Pile, suits: &[Suit], ranks: &[Rank]) {
+    for suit in suits {
+        for rank in ranks {
+            cards.push(Card::new(*rank, *suit));
+        }
+    }
+}
+
+impl Game {
+    pub(crate) fn setup() -> Self {
+        Self::setup_with_rules(RuleSet::default())
+    }
+
+    /// Like [`Game::setup`], but shuffles the dungeon with an RNG seeded
+    /// from `seed` so the resulting game can be reproduced exactly.
+    pub(crate) fn setup_seeded(seed: u64) -> Self {
+        Self::setup_seeded_with_rules(seed, RuleSet::default())
+    }
+
+    pub(crate) fn setup_with_rules(rule_set: RuleSet) -> Self {
+        Self::setup_with_rng(&mut rand::thread_rng(), rule_set)
+    }
+
+    pub(crate) fn setup_seeded_with_rules(seed: u64, rule_set: RuleSet) -> Self {
+        Self::setup_with_rng(&mut StdRng::seed_from_u64(seed), rule_set)
+    }
+
+    fn setup_with_rng(rng: &mut impl Rng, rule_set: RuleSet) -> Self {
+        use cardpack::cards::{rank::ACE, suit::*};
+
+        let black_suits = Suit::from_array(&[SPADES, CLUBS]);
+        let red_suits = Suit::from_array(&[HEARTS, DIAMONDS]);
+
+        let mut dungeon: Pile = Pile::default();
+        fold_in(&mut dungeon, &black_suits, &rule_set.deck.black_ranks());
+        fold_in(&mut dungeon, &red_suits, &rule_set.deck.red_ranks());
+        if rule_set.deck.jokers {
+            fold_in(&mut dungeon, &Suit::from_array(&[HEARTS]), &[ACE, ACE]);
+        }
+
+        let mut cards = dungeon.cards().to_vec();
+        cards.shuffle(rng);
+        let mut shuffled = Pile::default();
+        for card in cards {
+            shuffled.push(card);
+        }
+
+        Self {
+            dungeon: shuffled,
+            weapon: None,
+            weakest_killed: None,
+            just_avoided_room: false,
+            room: Pile::default(),
+            health: rule_set.max_health,
+            rule_set,
+        }
+    }
+
+    /// A read-only snapshot of the game for strategies to decide on.
+    pub(crate) fn view(&self) -> GameView {
+        GameView {
+            health: self.health,
+            weapon: self.weapon,
+            weakest_killed: self.weakest_killed,
+            dungeon_remaining: self.dungeon.len() - (self.rule_set.room_size - self.room.len()),
+            dungeon: self.dungeon.cards().to_vec(),
+            room: self.room.cards().to_vec(),
+            just_avoided_room: self.just_avoided_room,
+            max_health: self.rule_set.max_health,
+            room_size: self.rule_set.room_size,
+        }
+    }
+
+    /// The maximum final score still achievable from the current position,
+    /// found by exhaustive search over the fixed dungeon order.
+    pub(crate) fn best_score(&self) -> Result<i32> {
+        crate::solver::best_score(
+            self.dungeon.cards(),
+            self.room.cards(),
+            self.health,
+            self.weapon,
+            self.weakest_killed,
+            self.just_avoided_room,
+            self.rule_set.max_health,
+            self.rule_set.room_size,
+        )
+    }
+
+    /// Writes the current game to `path` as JSON, so it can be resumed later.
+    pub(crate) fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+
+    /// Reads back a game previously written by [`Game::save`].
+    pub(crate) fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Plays the game to completion, asking `strategy` for every decision
+    /// instead of prompting the terminal directly.
+    pub(crate) fn play_with(&mut self, strategy: &mut dyn Strategy) -> Result<GameResult> {
+        loop {
+            self.enter()?;
+            if !self.just_avoided_room && strategy.avoid_room(&self.view())? {
+                self.dungeon.append(&self.room);
+                self.just_avoided_room = true;
+                self.room = Pile::default();
+                continue;
+            }
+            self.just_avoided_room = false;
+            // Normally the room is played down to its last card, which
+            // carries over into the next one. But once the dungeon is spent
+            // there is no next room to carry it into, so that last card has
+            // to be played too for the game to ever reach `Won`.
+            while !self.room.is_empty() && (self.room.len() > 1 || self.dungeon.is_empty()) {
+                let selection = strategy.pick_card(&self.view())?;
+                let card = self.room.get(selection).unwrap().clone();
+                self.apply_card(&card, strategy)?;
+                if self.health == 0 {
+                    return Ok(GameResult {
+                        outcome: Outcome::Lost,
+                        score: (self
+                            .dungeon
+                            .cards()
+                            .iter()
+                            .filter(|card| card.suit.name() == SPADES || card.suit.name() == CLUBS)
+                            .map(weight)
+                            .collect::<Result<Vec<_>>>()?
+                            .into_iter()
+                            .sum::<u8>() as i32)
+                            .neg(),
+                    });
+                }
+                self.room.remove(selection);
+                if self.dungeon.is_empty() && self.room.is_empty() {
+                    return Ok(GameResult {
+                        outcome: Outcome::Won,
+                        score: (self.health
+                            + (if card.suit.name() == HEARTS {
+                                weight(&card)?
+                            } else {
+                                0
+                            })) as i32,
+                    });
+                }
+            }
+        }
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        if self.room.len() > 1 {
+            bail!(Error::RoomUnfinished);
+        }
+        if self.dungeon.is_empty() {
+            bail!(Error::DungeonFinished);
+        }
+        let room_size = self.rule_set.room_size;
+        let new_cards = self
+            .dungeon
+            .draw(self.dungeon.len().min(room_size - self.room.len()))
+            .unwrap();
+        for card in new_cards {
+            self.room.push(card);
+        }
+        debug_assert_eq!(self.room.len(), room_size);
+        Ok(())
+    }
+
+    fn apply_card(&mut self, card: &Card, strategy: &mut dyn Strategy) -> Result<()> {
+        match suit_code(card).as_str() {
+            "D" => self.equip(weight(card)?),
+            "S" | "C" => self.fight(weight(card)?, strategy),
+            "H" => self.heal(weight(card)?),
+            _ => bail!(Error::InvalidCardSuit),
+        }
+    }
+
+    fn equip(&mut self, weapon: u8) -> Result<()> {
+        self.weapon = Some(weapon);
+        self.weakest_killed = None;
+        Ok(())
+    }
+
+    fn fight(&mut self, monster: u8, strategy: &mut dyn Strategy) -> Result<()> {
+        match self.rule_set.combat {
+            CombatRules::Deterministic => self.fight_deterministic(monster, strategy),
+            CombatRules::D20(stats) => self.fight_d20(monster, stats),
+        }
+    }
+
+    fn fight_deterministic(&mut self, monster: u8, strategy: &mut dyn Strategy) -> Result<()> {
+        let blocked = if let Some(weapon) = self.weapon {
+            if self
+                .weakest_killed
+                .map(|prev| monster < prev)
+                .unwrap_or(true)
+                && strategy.use_weapon(&self.view(), monster)?
+            {
+                self.weakest_killed = Some(monster);
+                weapon
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+
+        let damage = monster.saturating_sub(blocked);
+        self.health = self.health.saturating_sub(damage);
+        Ok(())
+    }
+
+    fn fight_d20(&mut self, monster: u8, stats: PlayerStats) -> Result<()> {
+        let hit_stat = stats
+            .body
+            .saturating_add(stats.strength)
+            .saturating_add(self.weapon.unwrap_or(0));
+        let defense_stat = stats.body.saturating_add(stats.toughness);
+
+        let (hit, margin) = do_challenge(hit_stat);
+        let effective_weight = if hit { monster.saturating_sub(margin) } else { monster };
+
+        let (defended, _) = do_challenge(defense_stat);
+        let damage = if defended { 0 } else { effective_weight };
+        self.health = self.health.saturating_sub(damage);
+        Ok(())
+    }
+
+    fn heal(&mut self, potion: u8) -> Result<()> {
+        self.health = self.rule_set.max_health.min(self.health.saturating_add(potion));
+        Ok(())
+    }
+}
+
+pub(crate) fn suit_code(card: &Card) -> String {
+    card.suit.name.index_default()
+}
+
+pub(crate) fn weight(card: &Card) -> Result<u8> {
+    let weight = match card.rank.index_default().as_str() {
+        "T" => 10,
+        "J" => 11,
+        "Q" => 12,
+        "K" => 13,
+        "A" => 14,
+        r => r.parse()?,
+    };
+    Ok(weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup() {
+        let game = Game::setup();
+        assert_eq!(game.dungeon.len(), 44);
+    }
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let game = Game::setup_seeded(1);
+        let path = std::env::temp_dir().join("scoundrel_test_save_load_round_trip.json");
+
+        game.save(&path).unwrap();
+        let loaded = Game::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.dungeon.len(), game.dungeon.len());
+        assert_eq!(loaded.health, game.health);
+        assert_eq!(loaded.weapon, game.weapon);
+    }
+
+    #[test]
+    fn test_setup_seeded_is_reproducible() {
+        fn order(game: &Game) -> Vec<CardDto> {
+            game.dungeon.cards().iter().map(CardDto::from).collect()
+        }
+
+        let a = Game::setup_seeded(42);
+        let b = Game::setup_seeded(42);
+        assert_eq!(
+            order(&a).iter().map(|c| (&c.rank, &c.suit)).collect::<Vec<_>>(),
+            order(&b).iter().map(|c| (&c.rank, &c.suit)).collect::<Vec<_>>(),
+        );
+
+        let c = Game::setup_seeded(43);
+        assert_ne!(
+            order(&a).iter().map(|c| (&c.rank, &c.suit)).collect::<Vec<_>>(),
+            order(&c).iter().map(|c| (&c.rank, &c.suit)).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_setup_with_rules_deck_and_room_size() {
+        use crate::ruleset::{DeckConfig, RankRange};
+
+        let rule_set = RuleSet {
+            max_health: 30,
+            room_size: 6,
+            deck: DeckConfig {
+                black_ranks: RankRange::Full,
+                red_ranks: RankRange::Full,
+                jokers: true,
+            },
+            ..RuleSet::default()
+        };
+
+        let mut game = Game::setup_with_rules(rule_set);
+        // 13 ranks * 2 black suits + 13 ranks * 2 red suits + 2 joker potions.
+        assert_eq!(game.dungeon.len(), 54);
+        assert_eq!(game.health, 30);
+
+        game.enter().unwrap();
+        assert_eq!(game.room.len(), 6);
+    }
+
+    struct AlwaysFirst;
+
+    impl Strategy for AlwaysFirst {
+        fn avoid_room(&mut self, _view: &GameView) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn pick_card(&mut self, _view: &GameView) -> Result<usize> {
+            Ok(0)
+        }
+
+        fn use_weapon(&mut self, _view: &GameView, _monster: u8) -> Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_play_with_reaches_won_on_an_empty_dungeon() {
+        use cardpack::cards::rank::{THREE, TWO};
+
+        let mut dungeon = Pile::default();
+        dungeon.push(Card::new(TWO, HEARTS));
+        dungeon.push(Card::new(THREE, HEARTS));
+
+        let mut game = Game {
+            dungeon,
+            weapon: None,
+            weakest_killed: None,
+            just_avoided_room: false,
+            room: Pile::default(),
+            health: 10,
+            rule_set: RuleSet { room_size: 2, ..RuleSet::default() },
+        };
+
+        let result = game.play_with(&mut AlwaysFirst).unwrap();
+        assert!(matches!(result.outcome, Outcome::Won));
+        // Both cards are hearts: health goes 10 -> 12 -> 15, plus the final
+        // card's weight again as the win bonus.
+        assert_eq!(result.score, 18);
+    }
+}
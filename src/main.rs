@@ -1,231 +1,172 @@
-use std::{fmt::Write, ops::Neg};
+mod cli;
+mod game;
+mod ruleset;
+mod solver;
+mod strategy;
 
-use cardpack::{Card, Named, Pile, Rank, Suit, CLUBS, HEARTS, SPADES};
-use color_eyre::{eyre::bail, Result};
-use dialoguer::{Confirm, Select};
-
-const MAX_HEALTH: u8 = 20;
-const ROOM_SIZE: usize = 4;
-
-struct GameResult {
-    outcome: Outcome,
-    score: i32,
-}
-
-#[derive(Debug)]
-enum Outcome {
-    Won,
-    Lost,
-}
+use std::collections::BTreeMap;
+use std::fs::File;
 
-struct Game {
-    dungeon: Pile,
-    weapon: Option<u8>,
-    weakest_killed: Option<u8>,
-    just_avoided_room: bool,
-    room: Pile,
-    health: u8,
-}
+use clap::Parser;
+use color_eyre::{eyre::bail, Result};
 
-#[derive(Debug, thiserror::Error)]
-enum Error {
-    #[error("dungeon finished")]
-    DungeonFinished,
-    #[error("room unfinished")]
-    RoomUnfinished,
-    #[error("invalid card suit")]
-    InvalidCardSuit,
-}
+use cli::{Cli, Command, StrategyKind};
+use game::{Game, Outcome};
+use ruleset::CombatRules;
+use strategy::{Hint, Interactive, Quit, Quittable, Recording, Replay, ReplayLog, Strategy};
 
-fn fold_in(cards: &mut Pile, suits: &[Suit], ranks: &[Rank]) {
-    for suit in suits {
-        for rank in ranks {
-            cards.push(Card::new(*rank, *suit));
-        }
-    }
-}
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Simulate { games, strategy }) => simulate(games, strategy),
+        None => {
+            if let Some(path) = cli.replay {
+                return replay(path);
+            }
 
-impl Game {
-    fn setup() -> Self {
-        use cardpack::cards::{rank::*, suit::*};
-
-        let all_ranks = Rank::generate_french_ranks();
-        let numbered_ranks =
-            Rank::from_array(&[TEN, NINE, EIGHT, SEVEN, SIX, FIVE, FOUR, THREE, TWO]);
-        let black_suits = Suit::from_array(&[SPADES, CLUBS]);
-        let red_suits = Suit::from_array(&[HEARTS, DIAMONDS]);
-
-        let mut dungeon: Pile = Pile::default();
-        fold_in(&mut dungeon, &black_suits, &all_ranks);
-        fold_in(&mut dungeon, &red_suits, &numbered_ranks);
-
-        Self {
-            dungeon: dungeon.shuffle(),
-            weapon: None,
-            weakest_killed: None,
-            just_avoided_room: false,
-            room: Pile::default(),
-            health: MAX_HEALTH,
-        }
-    }
+            if let Some(path) = &cli.load {
+                let mut game = Game::load(path)?;
+                if let Some(result) = play_resumable(&mut game, &mut Interactive, &cli.save)? {
+                    println!("{:?}! Score: {}", result.outcome, result.score);
+                }
+                return Ok(());
+            }
 
-    fn prompt(&self, question: Option<&str>) -> Result<String> {
-        let mut prompt = format!(
-            "H: {:>2}, W: {:>2} (M: {:>2}), D: {:>2}, R: {}",
-            self.health,
-            self.weapon.unwrap_or_default(),
-            self.weakest_killed.unwrap_or_default(),
-            self.dungeon.len() - (ROOM_SIZE - self.room.len()),
-            self.room
-        );
-        if let Some(q) = question {
-            write!(prompt, ", {q}")?;
-        }
-        Ok(prompt)
-    }
+            let seed = cli.seed.unwrap_or_else(|| rand::random());
+            println!("seed: {seed}");
+            let rule_set = cli.rule_set()?;
 
-    fn play(&mut self) -> Result<GameResult> {
-        loop {
-            self.enter()?;
-            if !self.just_avoided_room {
-                let avoid = Confirm::new()
-                    .with_prompt(self.prompt(Some("avoid?"))?)
-                    .interact()?;
-                if avoid {
-                    self.dungeon.append(&self.room);
-                    self.just_avoided_room = true;
-                    self.room = Pile::default();
-                    continue;
+            if cli.solve {
+                if !matches!(rule_set.combat, CombatRules::Deterministic) {
+                    bail!("--solve only supports the deterministic combat rule set");
                 }
+                let game = Game::setup_seeded_with_rules(seed, rule_set);
+                println!("best reachable score: {}", game.best_score()?);
+                return Ok(());
             }
-            self.just_avoided_room = false;
-            while self.room.len() > 1 {
-                let selection = Select::new()
-                    .with_prompt(self.prompt(None)?)
-                    .items(self.room.cards())
-                    .interact()?;
-                let card = self.room.get(selection).unwrap().clone();
-                self.apply_card(&card)?;
-                if self.health == 0 {
-                    return Ok(GameResult {
-                        outcome: Outcome::Lost,
-                        score: (self
-                            .dungeon
-                            .cards()
-                            .iter()
-                            .filter(|card| card.suit.name() == SPADES || card.suit.name() == CLUBS)
-                            .map(weight)
-                            .collect::<Result<Vec<_>>>()?
-                            .into_iter()
-                            .sum::<u8>() as i32)
-                            .neg(),
-                    });
-                }
-                self.room.remove(selection);
-                if self.dungeon.is_empty() && self.room.is_empty() {
-                    return Ok(GameResult {
-                        outcome: Outcome::Won,
-                        score: (self.health
-                            + (if card.suit.name() == HEARTS {
-                                weight(&card)?
-                            } else {
-                                0
-                            })) as i32,
-                    });
+
+            if cli.record.is_some() && !matches!(rule_set.combat, CombatRules::Deterministic) {
+                bail!("--record only supports the deterministic combat rule set, since d20 combat rolls aren't reproducible");
+            }
+
+            let mut game = Game::setup_seeded_with_rules(seed, rule_set);
+            let mut interactive: Box<dyn Strategy> = if cli.hint {
+                Box::new(Hint)
+            } else {
+                Box::new(Interactive)
+            };
+
+            if let Some(path) = cli.record {
+                let mut recording = Recording::new(interactive.as_mut());
+                if let Some(result) = play_resumable(&mut game, &mut recording, &cli.save)? {
+                    let log = ReplayLog {
+                        seed,
+                        rule_set,
+                        moves: recording.moves,
+                    };
+                    serde_json::to_writer_pretty(File::create(path)?, &log)?;
+                    println!("{:?}! Score: {}", result.outcome, result.score);
                 }
+            } else if let Some(result) = play_resumable(&mut game, interactive.as_mut(), &cli.save)? {
+                println!("{:?}! Score: {}", result.outcome, result.score);
             }
-        }
-    }
 
-    fn enter(&mut self) -> Result<()> {
-        if self.room.len() > 1 {
-            bail!(Error::RoomUnfinished);
-        }
-        if self.dungeon.is_empty() {
-            bail!(Error::DungeonFinished);
-        }
-        let new_cards = self
-            .dungeon
-            .draw(self.dungeon.len().min(ROOM_SIZE - self.room.len()))
-            .unwrap();
-        for card in new_cards {
-            self.room.push(card);
+            Ok(())
         }
-        debug_assert_eq!(self.room.len(), ROOM_SIZE);
-        Ok(())
     }
+}
 
-    fn apply_card(&mut self, card: &Card) -> Result<()> {
-        match card.suit.name.index_default().as_str() {
-            "D" => self.equip(weight(card)?),
-            "S" | "C" => self.fight(weight(card)?),
-            "H" => self.heal(weight(card)?),
-            _ => bail!(Error::InvalidCardSuit),
+/// Plays `game` with `strategy`, wrapped so the player can save and quit
+/// mid-dungeon (whenever `save` is set) instead of only being able to save
+/// an already-finished game. Returns `None` if they quit that way, in which
+/// case `game` has already been written to `save`.
+fn play_resumable(
+    game: &mut Game,
+    strategy: &mut dyn Strategy,
+    save: &Option<std::path::PathBuf>,
+) -> Result<Option<game::GameResult>> {
+    let mut quittable = Quittable::new(strategy, save.is_some());
+    match game.play_with(&mut quittable) {
+        Ok(result) => Ok(Some(result)),
+        Err(err) if err.downcast_ref::<Quit>().is_some() => {
+            if let Some(path) = save {
+                game.save(path)?;
+                println!("game saved to {}", path.display());
+            }
+            Ok(None)
         }
+        Err(err) => Err(err),
     }
+}
 
-    fn equip(&mut self, weapon: u8) -> Result<()> {
-        self.weapon = Some(weapon);
-        self.weakest_killed = None;
-        Ok(())
+/// Deserializes a recorded sequence of moves and re-runs them exactly.
+fn replay(path: std::path::PathBuf) -> Result<()> {
+    let log: ReplayLog = serde_json::from_reader(File::open(path)?)?;
+    if !matches!(log.rule_set.combat, CombatRules::Deterministic) {
+        bail!("--replay only supports logs recorded under the deterministic combat rule set, since d20 combat rolls aren't reproducible");
     }
+    let mut game = Game::setup_seeded_with_rules(log.seed, log.rule_set);
+    let mut strategy = Replay::new(log.moves);
+    let result = game.play_with(&mut strategy)?;
+    println!("{:?}! Score: {}", result.outcome, result.score);
+    Ok(())
+}
 
-    fn fight(&mut self, monster: u8) -> Result<()> {
-        let blocked = if let Some(weapon) = self.weapon {
-            if self
-                .weakest_killed
-                .map(|prev| monster < prev)
-                .unwrap_or(true)
-                && Confirm::new()
-                    .with_prompt(self.prompt(Some("use weapon?"))?)
-                    .interact()?
-            {
-                self.weakest_killed = Some(monster);
-                weapon
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-
-        let damage = monster.saturating_sub(blocked);
-        self.health = self.health.saturating_sub(damage);
-        Ok(())
+/// Plays `games` headless rounds with `strategy`, one per seed in `0..games`,
+/// and prints win rate, mean/median score, and a rough score distribution.
+fn simulate(games: usize, strategy: StrategyKind) -> Result<()> {
+    if games == 0 {
+        bail!("--games must be at least 1");
     }
 
-    fn heal(&mut self, potion: u8) -> Result<()> {
-        self.health = MAX_HEALTH.min(self.health + potion);
-        Ok(())
+    let mut wins = 0usize;
+    let mut scores = Vec::with_capacity(games);
+    for seed in 0..games as u64 {
+        let mut game = Game::setup_seeded(seed);
+        let mut strategy = strategy.build();
+        let result = game.play_with(strategy.as_mut())?;
+        if matches!(result.outcome, Outcome::Won) {
+            wins += 1;
+        }
+        scores.push(result.score);
     }
-}
 
-fn weight(card: &Card) -> Result<u8> {
-    let weight = match card.rank.index_default().as_str() {
-        "T" => 10,
-        "J" => 11,
-        "Q" => 12,
-        "K" => 13,
-        "A" => 14,
-        r => r.parse()?,
-    };
-    Ok(weight)
-}
+    scores.sort_unstable();
+    let mean = scores.iter().sum::<i32>() as f64 / games as f64;
+    let median = scores[scores.len() / 2];
+
+    println!("games played: {games}");
+    println!("win rate: {:.1}%", 100.0 * wins as f64 / games as f64);
+    println!("mean score: {mean:.2}");
+    println!("median score: {median}");
+    println!("min/max score: {}/{}", scores[0], scores[scores.len() - 1]);
+    print_histogram(&scores);
 
-fn main() -> Result<()> {
-    let mut game = Game::setup();
-    let result = game.play()?;
-    println!("{:?}! Score: {}", result.outcome, result.score);
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Prints a coarse ASCII histogram of `scores`, bucketed into ten bins
+/// spanning the observed range, with bars scaled to the busiest bucket.
+fn print_histogram(scores: &[i32]) {
+    const BUCKETS: i32 = 10;
+    const MAX_BAR: usize = 40;
 
-    #[test]
-    fn test_setup() {
-        let game = Game::setup();
-        assert_eq!(game.dungeon.len(), 44);
+    let min = scores[0];
+    let max = scores[scores.len() - 1];
+    let bucket_width = (max - min) / BUCKETS + 1;
+
+    let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+    for &score in scores {
+        let bucket = (score - min) / bucket_width;
+        *counts.entry(bucket).or_insert(0) += 1;
+    }
+    let busiest = *counts.values().max().unwrap_or(&1);
+
+    println!("score distribution:");
+    for (bucket, count) in counts {
+        let lo = min + bucket * bucket_width;
+        let hi = lo + bucket_width;
+        let bar_len = count * MAX_BAR / busiest;
+        println!("  [{lo:>4}, {hi:>4}): {:<width$} {count}", "#".repeat(bar_len), width = MAX_BAR);
     }
 }
@@ -0,0 +1,157 @@
+use std::{fs::File, path::Path};
+
+use cardpack::Rank;
+use color_eyre::Result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const DEFAULT_MAX_HEALTH: u8 = 20;
+pub(crate) const DEFAULT_ROOM_SIZE: usize = 4;
+
+/// Which ranks a suit contributes to the dungeon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum RankRange {
+    /// 2 through 10, the original numbered red suits.
+    Numbered,
+    /// 2 through Ace, the original black suits.
+    Full,
+}
+
+impl RankRange {
+    fn ranks(self) -> Vec<Rank> {
+        use cardpack::cards::rank::*;
+
+        match self {
+            RankRange::Numbered => {
+                Rank::from_array(&[TEN, NINE, EIGHT, SEVEN, SIX, FIVE, FOUR, THREE, TWO])
+            }
+            RankRange::Full => Rank::generate_french_ranks(),
+        }
+    }
+}
+
+/// Which cards go into the dungeon pile.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct DeckConfig {
+    /// Ranks dealt for the monster suits (spades and clubs).
+    pub(crate) black_ranks: RankRange,
+    /// Ranks dealt for the weapon/potion suits (diamonds and hearts).
+    pub(crate) red_ranks: RankRange,
+    /// `cardpack` has no dedicated Joker card, so this adds two extra
+    /// high-value potions to the dungeon as a stand-in for a jokers variant.
+    pub(crate) jokers: bool,
+}
+
+impl Default for DeckConfig {
+    fn default() -> Self {
+        Self {
+            black_ranks: RankRange::Full,
+            red_ranks: RankRange::Numbered,
+            jokers: false,
+        }
+    }
+}
+
+impl DeckConfig {
+    pub(crate) fn black_ranks(&self) -> Vec<Rank> {
+        self.black_ranks.ranks()
+    }
+
+    pub(crate) fn red_ranks(&self) -> Vec<Rank> {
+        self.red_ranks.ranks()
+    }
+}
+
+/// Player attributes used by the d20 combat variant.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct PlayerStats {
+    pub(crate) body: u8,
+    pub(crate) strength: u8,
+    pub(crate) toughness: u8,
+}
+
+/// How a monster card is resolved in combat.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum CombatRules {
+    /// The original Scoundrel rule: flat subtraction, with the weapon only
+    /// blocking monsters weaker than the last one it killed.
+    Deterministic,
+    /// A d20 attribute-check variant, in the style of the dungeon-slayer
+    /// rules: a hit check against the player's stats (plus weapon) decides
+    /// how much a monster's weight is reduced, and a separate defense check
+    /// decides whether that weight is taken as damage at all.
+    D20(PlayerStats),
+}
+
+/// The tunable knobs of a game. `Default` matches the original Scoundrel
+/// rules.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct RuleSet {
+    pub(crate) combat: CombatRules,
+    pub(crate) max_health: u8,
+    pub(crate) room_size: usize,
+    pub(crate) deck: DeckConfig,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            combat: CombatRules::Deterministic,
+            max_health: DEFAULT_MAX_HEALTH,
+            room_size: DEFAULT_ROOM_SIZE,
+            deck: DeckConfig::default(),
+        }
+    }
+}
+
+impl RuleSet {
+    /// Loads a rule set from a JSON config file, the way the Dominion server
+    /// lets clients swap in a different kingdom-card supply at setup.
+    pub(crate) fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+}
+
+/// The pure resolution logic behind [`do_challenge`], split out so it can be
+/// tested without depending on the RNG.
+fn resolve_challenge(roll: u8, stat: u8) -> (bool, u8) {
+    match roll {
+        20 => (false, 0),
+        1 => (true, roll),
+        roll if roll <= stat => (true, stat - roll),
+        _ => (false, 0),
+    }
+}
+
+/// Rolls a d20 against `stat` and returns `(success, margin)`. A natural 20
+/// always fails with margin 0; a natural 1 always succeeds with margin
+/// equal to the roll; otherwise it succeeds when the roll is at most
+/// `stat`, with margin `stat - roll`.
+pub(crate) fn do_challenge(stat: u8) -> (bool, u8) {
+    resolve_challenge(rand::thread_rng().gen_range(1..=20), stat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_challenge;
+
+    #[test]
+    fn test_challenge_nat_one_always_succeeds() {
+        assert_eq!(resolve_challenge(1, 0), (true, 1));
+    }
+
+    #[test]
+    fn test_challenge_nat_twenty_always_fails() {
+        assert_eq!(resolve_challenge(20, 20), (false, 0));
+    }
+
+    #[test]
+    fn test_challenge_roll_equals_stat_succeeds_with_zero_margin() {
+        assert_eq!(resolve_challenge(10, 10), (true, 0));
+    }
+
+    #[test]
+    fn test_challenge_roll_above_stat_fails() {
+        assert_eq!(resolve_challenge(15, 10), (false, 0));
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use cardpack::Card;
+use color_eyre::Result;
+
+use crate::game::{suit_code, weight};
+
+/// A card reduced to the only two facts the solver cares about: its suit
+/// category and its weight. Cheaper to clone and hash than a `cardpack::Card`.
+type SolverCard = (char, u8);
+
+fn to_solver_cards(cards: &[Card]) -> Result<Vec<SolverCard>> {
+    cards
+        .iter()
+        .map(|card| {
+            let suit = suit_code(card).chars().next().unwrap_or('?');
+            Ok((suit, weight(card)?))
+        })
+        .collect()
+}
+
+/// The full search state. Unlike a live `Game`, `dungeon` isn't just an
+/// index into the original shuffle: avoiding a room requeues its cards at
+/// the back, so the remaining draw order itself can diverge between
+/// branches and has to be carried in full to canonicalize the state.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    dungeon: Vec<SolverCard>,
+    room: Vec<SolverCard>,
+    health: u8,
+    weapon: Option<u8>,
+    weakest_killed: Option<u8>,
+    just_avoided_room: bool,
+}
+
+impl State {
+    fn canonical(mut self) -> Self {
+        self.room.sort_unstable();
+        self
+    }
+}
+
+/// Depth-first search with memoization over the space described in the
+/// request: `(dungeon, room, health, weapon, weakest_killed,
+/// just_avoided_room)`. Branches are the legal actions at each step: avoid
+/// the room (only when it wasn't just avoided), pick any card in the room,
+/// and for monsters the binary choice of using the weapon.
+struct Solver {
+    memo: HashMap<State, i32>,
+    max_health: u8,
+    room_size: usize,
+}
+
+impl Solver {
+    fn new(max_health: u8, room_size: usize) -> Self {
+        Self {
+            memo: HashMap::new(),
+            max_health,
+            room_size,
+        }
+    }
+
+    /// Best score reachable from a state where the room still needs
+    /// (re)filling, i.e. right before `Game::enter`.
+    fn step(&mut self, state: State) -> i32 {
+        let state = state.canonical();
+        if let Some(&score) = self.memo.get(&state) {
+            return score;
+        }
+
+        let score = if state.dungeon.is_empty() {
+            // Reached only from the very first query (an avoided room always
+            // puts its cards back into the dungeon): the room is empty and there's nothing left to deal into it, so
+            // this position is already won. A win reached by playing down
+            // the final room instead is scored in `continue_after_pick`,
+            // which also adds the last card's bonus if it was a heart.
+            state.health as i32
+        } else {
+            let mut filled = state.clone();
+            let draw = filled.dungeon.len().min(self.room_size - filled.room.len());
+            let drawn = filled.dungeon.drain(..draw).collect::<Vec<_>>();
+            filled.room.extend(drawn);
+            self.decide_room(filled)
+        };
+
+        self.memo.insert(state, score);
+        score
+    }
+
+    /// The avoid-or-not decision made once per freshly entered room.
+    fn decide_room(&mut self, state: State) -> i32 {
+        let mut best = i32::MIN;
+
+        if !state.just_avoided_room {
+            let mut avoided = state.clone();
+            let room = std::mem::take(&mut avoided.room);
+            avoided.dungeon.extend(room);
+            avoided.just_avoided_room = true;
+            best = best.max(self.step(avoided));
+        }
+
+        let mut stay = state;
+        stay.just_avoided_room = false;
+        best.max(self.pick_loop(stay))
+    }
+
+    /// Keeps picking cards from the room until only one is left, at which
+    /// point the next room has to be entered — unless the dungeon is
+    /// already spent, in which case there's no next room to carry that last
+    /// card into, so it has to be played too for a win to ever register.
+    fn pick_loop(&mut self, state: State) -> i32 {
+        if state.room.is_empty() || (state.room.len() == 1 && !state.dungeon.is_empty()) {
+            return self.step(state);
+        }
+
+        let state = state.canonical();
+        if let Some(&score) = self.memo.get(&state) {
+            return score;
+        }
+
+        let score = (0..state.room.len())
+            .map(|index| self.evaluate_pick(&state, index))
+            .max()
+            .unwrap_or(i32::MIN);
+
+        self.memo.insert(state, score);
+        score
+    }
+
+    /// Best score obtainable by picking `index` out of the current room.
+    fn evaluate_pick(&mut self, state: &State, index: usize) -> i32 {
+        let mut base = state.clone();
+        let (suit, value) = base.room.remove(index);
+
+        self.branches_after(base, suit, value)
+            .into_iter()
+            .map(|next| self.continue_after_pick(next, suit, value))
+            .max()
+            .unwrap_or(i32::MIN)
+    }
+
+    /// Applies a picked card's effect, forking into both outcomes when the
+    /// player has a real choice to make (using the weapon on a monster).
+    fn branches_after(&self, mut state: State, suit: char, value: u8) -> Vec<State> {
+        match suit {
+            'D' => {
+                state.weapon = Some(value);
+                state.weakest_killed = None;
+                vec![state]
+            }
+            'H' => {
+                state.health = self.max_health.min(state.health.saturating_add(value));
+                vec![state]
+            }
+            'S' | 'C' => {
+                let eligible = state
+                    .weapon
+                    .is_some_and(|_| state.weakest_killed.map(|prev| value < prev).unwrap_or(true));
+                if eligible {
+                    let weapon = state.weapon.unwrap();
+                    let mut used = state.clone();
+                    used.weakest_killed = Some(value);
+                    used.health = used.health.saturating_sub(value.saturating_sub(weapon));
+
+                    let mut unused = state;
+                    unused.health = unused.health.saturating_sub(value);
+
+                    vec![used, unused]
+                } else {
+                    state.health = state.health.saturating_sub(value);
+                    vec![state]
+                }
+            }
+            _ => vec![state],
+        }
+    }
+
+    fn continue_after_pick(&mut self, next: State, suit: char, value: u8) -> i32 {
+        if next.health == 0 {
+            return self.lost_score(&next);
+        }
+        if next.dungeon.is_empty() && next.room.is_empty() {
+            let bonus = if suit == 'H' { i32::from(value) } else { 0 };
+            return next.health as i32 + bonus;
+        }
+        self.pick_loop(next)
+    }
+
+    fn lost_score(&self, state: &State) -> i32 {
+        -state
+            .dungeon
+            .iter()
+            .filter(|(suit, _)| matches!(suit, 'S' | 'C'))
+            .map(|(_, weight)| i32::from(*weight))
+            .sum::<i32>()
+    }
+}
+
+/// Computes the maximum achievable final score from the given position,
+/// via exhaustive memoized search. Exposed as `--solve` mode; the
+/// interactive `hint` annotations use [`pick_scores`] instead.
+pub(crate) fn best_score(
+    dungeon: &[Card],
+    room: &[Card],
+    health: u8,
+    weapon: Option<u8>,
+    weakest_killed: Option<u8>,
+    just_avoided_room: bool,
+    max_health: u8,
+    room_size: usize,
+) -> Result<i32> {
+    let state = State {
+        dungeon: to_solver_cards(dungeon)?,
+        room: to_solver_cards(room)?,
+        health,
+        weapon,
+        weakest_killed,
+        just_avoided_room,
+    };
+
+    let mut solver = Solver::new(max_health, room_size);
+    Ok(solver.pick_loop(state))
+}
+
+/// The best-case score for choosing each card currently in the room, in the
+/// same order as `room`. Used to annotate the interactive `hint` prompt.
+pub(crate) fn pick_scores(
+    dungeon: &[Card],
+    room: &[Card],
+    health: u8,
+    weapon: Option<u8>,
+    weakest_killed: Option<u8>,
+    max_health: u8,
+    room_size: usize,
+) -> Result<Vec<i32>> {
+    let state = State {
+        dungeon: to_solver_cards(dungeon)?,
+        room: to_solver_cards(room)?,
+        health,
+        weapon,
+        weakest_killed,
+        just_avoided_room: false,
+    };
+
+    let mut solver = Solver::new(max_health, room_size);
+    Ok((0..state.room.len())
+        .map(|index| solver.evaluate_pick(&state, index))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use cardpack::cards::{
+        rank::{FIVE, THREE},
+        suit::{DIAMONDS, HEARTS},
+    };
+    use cardpack::Card;
+
+    use super::best_score;
+
+    #[test]
+    fn test_best_score_tiny_room() {
+        // Empty dungeon, two cards left in the room: a 5 weapon and a 3
+        // potion. Both end up played, since there's no next room left to
+        // carry either one into. Picking the weapon first then the potion
+        // heals 10 -> 13 and, ending on a heart, adds its weight again as
+        // the win bonus: 16. Picking the potion first gives the same heal
+        // but ends on the weapon, with no bonus: 13. The weapon-first line
+        // wins.
+        let room = vec![Card::new(FIVE, DIAMONDS), Card::new(THREE, HEARTS)];
+        let score = best_score(&[], &room, 10, None, None, false, 20, 4).unwrap();
+        assert_eq!(score, 16);
+    }
+}
@@ -0,0 +1,323 @@
+use std::fmt::Write;
+
+use cardpack::Card;
+use color_eyre::{eyre::bail, Result};
+use dialoguer::{Confirm, Select};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{suit_code, weight};
+use crate::ruleset::RuleSet;
+
+/// A read-only snapshot of a [`Game`](crate::game::Game) passed to a
+/// [`Strategy`] so it can decide without touching the game's internals.
+pub(crate) struct GameView {
+    pub(crate) health: u8,
+    pub(crate) weapon: Option<u8>,
+    pub(crate) weakest_killed: Option<u8>,
+    pub(crate) dungeon_remaining: usize,
+    pub(crate) dungeon: Vec<Card>,
+    pub(crate) room: Vec<Card>,
+    pub(crate) just_avoided_room: bool,
+    pub(crate) max_health: u8,
+    pub(crate) room_size: usize,
+}
+
+impl GameView {
+    fn status_line(&self) -> String {
+        format!(
+            "H: {:>2}, W: {:>2} (M: {:>2}), D: {:>2}, R: {}",
+            self.health,
+            self.weapon.unwrap_or_default(),
+            self.weakest_killed.unwrap_or_default(),
+            self.dungeon_remaining,
+            self.room
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Replaces the interactive `dialoguer` prompts so a game can be played
+/// programmatically, e.g. from the headless simulator. Fallible so that
+/// `Interactive`'s terminal I/O errors and a desynced `Replay` log can
+/// propagate like everything else in the crate, instead of panicking.
+pub(crate) trait Strategy {
+    fn avoid_room(&mut self, view: &GameView) -> Result<bool>;
+    fn pick_card(&mut self, view: &GameView) -> Result<usize>;
+    fn use_weapon(&mut self, view: &GameView, monster: u8) -> Result<bool>;
+}
+
+/// The original behavior: ask the terminal via `dialoguer`.
+pub(crate) struct Interactive;
+
+impl Strategy for Interactive {
+    fn avoid_room(&mut self, view: &GameView) -> Result<bool> {
+        let mut prompt = view.status_line();
+        write!(prompt, ", avoid?").unwrap();
+        Ok(Confirm::new().with_prompt(prompt).interact()?)
+    }
+
+    fn pick_card(&mut self, view: &GameView) -> Result<usize> {
+        Ok(Select::new().with_prompt(view.status_line()).items(&view.room).interact()?)
+    }
+
+    fn use_weapon(&mut self, view: &GameView, _monster: u8) -> Result<bool> {
+        let mut prompt = view.status_line();
+        write!(prompt, ", use weapon?").unwrap();
+        Ok(Confirm::new().with_prompt(prompt).interact()?)
+    }
+}
+
+/// Like [`Interactive`], but annotates each card in the `pick_card` prompt
+/// with the best-case score the solver finds for choosing it.
+pub(crate) struct Hint;
+
+impl Strategy for Hint {
+    fn avoid_room(&mut self, view: &GameView) -> Result<bool> {
+        Interactive.avoid_room(view)
+    }
+
+    fn pick_card(&mut self, view: &GameView) -> Result<usize> {
+        let scores = crate::solver::pick_scores(
+            &view.dungeon,
+            &view.room,
+            view.health,
+            view.weapon,
+            view.weakest_killed,
+            view.max_health,
+            view.room_size,
+        )
+        .unwrap_or_default();
+
+        let items: Vec<String> = view
+            .room
+            .iter()
+            .enumerate()
+            .map(|(index, card)| match scores.get(index) {
+                Some(score) => format!("{card} (best: {score})"),
+                None => card.to_string(),
+            })
+            .collect();
+
+        Ok(Select::new().with_prompt(view.status_line()).items(&items).interact()?)
+    }
+
+    fn use_weapon(&mut self, view: &GameView, monster: u8) -> Result<bool> {
+        Interactive.use_weapon(view, monster)
+    }
+}
+
+/// Picks cards and weapon use uniformly at random, and avoids a room one
+/// time in ten. A useful baseline to benchmark smarter strategies against.
+pub(crate) struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn avoid_room(&mut self, _view: &GameView) -> Result<bool> {
+        Ok(rand::thread_rng().gen_ratio(1, 10))
+    }
+
+    fn pick_card(&mut self, view: &GameView) -> Result<usize> {
+        Ok(rand::thread_rng().gen_range(0..view.room.len()))
+    }
+
+    fn use_weapon(&mut self, _view: &GameView, _monster: u8) -> Result<bool> {
+        Ok(rand::random())
+    }
+}
+
+/// A simple heuristic: heal when hurt, take any weapon on offer, use the weapon
+/// whenever it's allowed, and otherwise prefer the weakest monster.
+pub(crate) struct GreedyStrategy;
+
+impl GreedyStrategy {
+    fn priority(view: &GameView, card: &Card) -> i32 {
+        match suit_code(card).as_str() {
+            "H" if view.health < view.max_health => 3,
+            "D" => 2,
+            "S" | "C" => match weight(card) {
+                Ok(monster) if view.weapon.is_some_and(|w| monster <= w) => 1,
+                Ok(monster) => -i32::from(monster),
+                Err(_) => 0,
+            },
+            _ => 0,
+        }
+    }
+}
+
+impl Strategy for GreedyStrategy {
+    fn avoid_room(&mut self, view: &GameView) -> Result<bool> {
+        Ok(view.health <= view.max_health / 4
+            && view.room.iter().all(|c| matches!(suit_code(c).as_str(), "S" | "C")))
+    }
+
+    fn pick_card(&mut self, view: &GameView) -> Result<usize> {
+        Ok(view
+            .room
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, card)| Self::priority(view, card))
+            .map(|(index, _)| index)
+            .unwrap_or_default())
+    }
+
+    fn use_weapon(&mut self, view: &GameView, monster: u8) -> Result<bool> {
+        Ok(view.weakest_killed.map(|prev| monster < prev).unwrap_or(true))
+    }
+}
+
+/// One decision made during a game, in the order `Strategy` is asked for it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum Move {
+    AvoidRoom(bool),
+    PickCard(usize),
+    UseWeapon(bool),
+}
+
+/// A recorded game: the seed and rule set it was dealt from, plus every move
+/// made, enough to re-run the exact same game later. The rule set matters
+/// because it changes the shuffle (deck composition), the room size (how
+/// many picks a room takes), and which moves combat asks for at all (the
+/// d20 variant never calls `use_weapon`).
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReplayLog {
+    pub(crate) seed: u64,
+    pub(crate) rule_set: RuleSet,
+    pub(crate) moves: Vec<Move>,
+}
+
+/// Wraps another strategy and records every decision it makes.
+pub(crate) struct Recording<'a> {
+    inner: &'a mut dyn Strategy,
+    pub(crate) moves: Vec<Move>,
+}
+
+impl<'a> Recording<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Strategy) -> Self {
+        Self {
+            inner,
+            moves: Vec::new(),
+        }
+    }
+}
+
+impl Strategy for Recording<'_> {
+    fn avoid_room(&mut self, view: &GameView) -> Result<bool> {
+        let decision = self.inner.avoid_room(view)?;
+        self.moves.push(Move::AvoidRoom(decision));
+        Ok(decision)
+    }
+
+    fn pick_card(&mut self, view: &GameView) -> Result<usize> {
+        let decision = self.inner.pick_card(view)?;
+        self.moves.push(Move::PickCard(decision));
+        Ok(decision)
+    }
+
+    fn use_weapon(&mut self, view: &GameView, monster: u8) -> Result<bool> {
+        let decision = self.inner.use_weapon(view, monster)?;
+        self.moves.push(Move::UseWeapon(decision));
+        Ok(decision)
+    }
+}
+
+/// Returned by [`Quittable`] when the player confirms a save-and-quit.
+/// `main` recognizes this via `downcast_ref` and saves the in-progress game
+/// instead of treating it as a failed run.
+#[derive(Debug, thiserror::Error)]
+#[error("quit requested")]
+pub(crate) struct Quit;
+
+/// Wraps another strategy and, when quitting is allowed, asks once before
+/// every decision whether to save and quit instead. Lets `--save` suspend a
+/// game mid-dungeon rather than only writing out an already-finished one.
+pub(crate) struct Quittable<'a> {
+    inner: &'a mut dyn Strategy,
+    can_quit: bool,
+}
+
+impl<'a> Quittable<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Strategy, can_quit: bool) -> Self {
+        Self { inner, can_quit }
+    }
+
+    fn check_quit(&self) -> Result<()> {
+        if self.can_quit && Confirm::new().with_prompt("save and quit?").default(false).interact()? {
+            bail!(Quit);
+        }
+        Ok(())
+    }
+}
+
+impl Strategy for Quittable<'_> {
+    fn avoid_room(&mut self, view: &GameView) -> Result<bool> {
+        self.check_quit()?;
+        self.inner.avoid_room(view)
+    }
+
+    fn pick_card(&mut self, view: &GameView) -> Result<usize> {
+        self.check_quit()?;
+        self.inner.pick_card(view)
+    }
+
+    fn use_weapon(&mut self, view: &GameView, monster: u8) -> Result<bool> {
+        self.check_quit()?;
+        self.inner.use_weapon(view, monster)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ReplayError {
+    #[error("replay log ended before the game did")]
+    LogExhausted,
+    #[error("expected {expected}, recorded log had {actual:?}")]
+    UnexpectedMove { expected: &'static str, actual: Move },
+}
+
+/// Replays a previously recorded sequence of moves exactly, in order. The
+/// log comes from untrusted/shared JSON (`--replay`), so a log that's too
+/// short or desynced from the game it's replayed against is a `Result`
+/// error, not a panic.
+pub(crate) struct Replay {
+    moves: std::vec::IntoIter<Move>,
+}
+
+impl Replay {
+    pub(crate) fn new(moves: Vec<Move>) -> Self {
+        Self {
+            moves: moves.into_iter(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Move> {
+        match self.moves.next() {
+            Some(move_) => Ok(move_),
+            None => bail!(ReplayError::LogExhausted),
+        }
+    }
+}
+
+impl Strategy for Replay {
+    fn avoid_room(&mut self, _view: &GameView) -> Result<bool> {
+        match self.next()? {
+            Move::AvoidRoom(decision) => Ok(decision),
+            actual => bail!(ReplayError::UnexpectedMove { expected: "AvoidRoom", actual }),
+        }
+    }
+
+    fn pick_card(&mut self, _view: &GameView) -> Result<usize> {
+        match self.next()? {
+            Move::PickCard(decision) => Ok(decision),
+            actual => bail!(ReplayError::UnexpectedMove { expected: "PickCard", actual }),
+        }
+    }
+
+    fn use_weapon(&mut self, _view: &GameView, _monster: u8) -> Result<bool> {
+        match self.next()? {
+            Move::UseWeapon(decision) => Ok(decision),
+            actual => bail!(ReplayError::UnexpectedMove { expected: "UseWeapon", actual }),
+        }
+    }
+}